@@ -0,0 +1,82 @@
+//! Crate-wide error type.
+//!
+//! Following the snafu/thiserror style (see e.g. nbsh's error module),
+//! every fallible PTY operation gets its own named variant instead of a
+//! vague boxed `dyn Error`, so callers can match on what actually failed
+//! and the UI can show something more useful than a panic.
+
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Opening the native PTY failed.
+    OpenPty {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Spawning the child process on the PTY slave failed.
+    SpawnProcess {
+        program: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Resizing the PTY to match the UI failed.
+    Resize {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Taking the PTY's input writer failed.
+    TakeWriter {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Cloning the PTY's output reader failed.
+    CloneReader {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Reading the PTY's output stream failed.
+    ReadOutput { source: std::io::Error },
+    /// Waiting on the child process failed.
+    Wait { source: std::io::Error },
+    /// Writing input to the child process failed.
+    WriteInput { source: std::io::Error },
+    /// Opening the session log file failed.
+    OpenLog {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::OpenPty { source } => write!(f, "failed to open PTY: {source}"),
+            Error::SpawnProcess { program, source } => {
+                write!(f, "failed to spawn {program}: {source}")
+            }
+            Error::Resize { source } => write!(f, "failed to resize PTY: {source}"),
+            Error::TakeWriter { source } => write!(f, "failed to take PTY writer: {source}"),
+            Error::CloneReader { source } => write!(f, "failed to clone PTY reader: {source}"),
+            Error::ReadOutput { source } => write!(f, "failed to read PTY output: {source}"),
+            Error::Wait { source } => write!(f, "failed to wait on child process: {source}"),
+            Error::WriteInput { source } => write!(f, "failed to write to PTY: {source}"),
+            Error::OpenLog { path, source } => {
+                write!(f, "failed to open log file {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::OpenPty { source } => Some(source.as_ref()),
+            Error::SpawnProcess { source, .. } => Some(source.as_ref()),
+            Error::Resize { source } => Some(source.as_ref()),
+            Error::TakeWriter { source } => Some(source.as_ref()),
+            Error::CloneReader { source } => Some(source.as_ref()),
+            Error::ReadOutput { source } => Some(source),
+            Error::Wait { source } => Some(source),
+            Error::WriteInput { source } => Some(source),
+            Error::OpenLog { source, .. } => Some(source),
+        }
+    }
+}
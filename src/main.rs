@@ -1,12 +1,29 @@
 use std::{
-    io::Read,
+    fs::File,
+    io::{Read, Write},
+    path::Path,
     process::exit,
     sync::{Arc, Mutex},
     thread::{self, JoinHandle},
 };
 
 use eframe::{App, egui};
-use portable_pty::{CommandBuilder, PtyPair, native_pty_system};
+use portable_pty::{CommandBuilder, PtyPair, PtySize, native_pty_system};
+
+mod error;
+mod progress;
+mod screen;
+
+use error::{Error, Result};
+use progress::{ProgressInfo, ProgressTracker};
+use screen::Screen;
+
+/// Locks a possibly-poisoned mutex, recovering the guard rather than
+/// panicking — a reader/wait thread panicking mid-write shouldn't also take
+/// down the UI thread.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
 
 /// Differentiates whether the incoming data came from stdout or stderr.
 #[derive(Debug, Clone, Copy)]
@@ -18,45 +35,217 @@ pub enum CommandStream {
 /// Minimal wrapper around a PTY that keeps collected output for rendering.
 pub struct PtyTerminal {
     pair: PtyPair,
-    buffer: Arc<Mutex<String>>,
+    screen: Arc<Mutex<Screen>>,
+    progress: Arc<Mutex<ProgressTracker>>,
+    writer: Option<Box<dyn Write + Send>>,
+    log: Arc<Mutex<Option<Box<dyn Write + Send>>>>,
+    last_error: Arc<Mutex<Option<String>>>,
     reader_thread: Option<JoinHandle<()>>,
     wait_thread: Option<JoinHandle<()>>,
+    cols: u16,
+    rows: u16,
 }
 
 impl PtyTerminal {
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new() -> Result<Self> {
         let system = native_pty_system();
-        let pair = system.openpty(Default::default())?;
+        let pair = system
+            .openpty(Default::default())
+            .map_err(|source| Error::OpenPty {
+                source: source.into(),
+            })?;
 
         Ok(Self {
             pair,
-            buffer: Arc::new(Mutex::new(String::new())),
+            screen: Arc::new(Mutex::new(Screen::new(80, 24))),
+            progress: Arc::new(Mutex::new(ProgressTracker::new())),
+            writer: None,
+            log: Arc::new(Mutex::new(None)),
+            last_error: Arc::new(Mutex::new(None)),
             reader_thread: None,
             wait_thread: None,
+            cols: 80,
+            rows: 24,
         })
     }
 
-    /// Ingests new text emitted by a subprocess, tagging stderr for clarity.
+    /// Writes raw bytes to the child's stdin, e.g. typed keystrokes or
+    /// ffmpeg's own control keys (`q` to stop, `\x03` for Ctrl-C).
+    pub fn send_input(&mut self, bytes: &[u8]) -> Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer
+                .write_all(bytes)
+                .and_then(|()| writer.flush())
+                .map_err(|source| Error::WriteInput { source })?;
+        }
+        Ok(())
+    }
+
+    /// Tees every raw byte chunk read from the PTY to `writer`, in addition
+    /// to feeding the in-memory screen/progress model. Because it sees the
+    /// bytes before the VTE layer collapses `\r`-driven progress updates,
+    /// this preserves a full, copy-pasteable record of ffmpeg's output.
+    pub fn set_log(&mut self, writer: Box<dyn Write + Send>) {
+        *lock(&self.log) = Some(writer);
+    }
+
+    /// Convenience wrapper around [`PtyTerminal::set_log`] that opens a
+    /// `<output>.log` file next to the chosen output path.
+    pub fn set_log_file_next_to(&mut self, output_path: &Path) -> Result<()> {
+        let mut log_path = output_path.as_os_str().to_owned();
+        log_path.push(".log");
+        let log_path = std::path::PathBuf::from(log_path);
+        let file = File::create(&log_path).map_err(|source| Error::OpenLog {
+            path: log_path.clone(),
+            source,
+        })?;
+        self.set_log(Box::new(std::io::BufWriter::new(file)));
+        Ok(())
+    }
+
+    /// Returns the most recently observed progress of the running command,
+    /// if ffmpeg has emitted enough of its status line to compute one.
+    pub fn progress(&self) -> Option<ProgressInfo> {
+        lock(&self.progress).progress()
+    }
+
+    /// Returns the message of the last terminal-level failure (a failed read
+    /// of the PTY output or a failed wait() on the child), if any has been
+    /// reported.
+    pub fn last_error(&self) -> Option<String> {
+        lock(&self.last_error).clone()
+    }
+
+    /// Tells the PTY slave the size of the terminal it is attached to, so that
+    /// the child process's `$COLUMNS`/`$LINES` (and ioctl-based queries, which
+    /// is how ffmpeg decides how to lay out its progress line) match reality.
+    pub fn resize(&mut self, cols: u16, rows: u16) -> Result<()> {
+        if cols == self.cols && rows == self.rows {
+            return Ok(());
+        }
+        self.pair
+            .master
+            .resize(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|source| Error::Resize {
+                source: source.into(),
+            })?;
+        self.cols = cols;
+        self.rows = rows;
+        lock(&self.screen).resize(cols as usize, rows as usize);
+        Ok(())
+    }
+
+    /// Ingests new bytes emitted by a subprocess, tagging stderr for clarity.
     pub fn push_output(&self, stream: CommandStream, chunk: &str) {
-        Self::write_chunk(&self.buffer, stream, chunk);
+        Self::write_chunk(
+            &self.screen,
+            &self.progress,
+            &self.log,
+            stream,
+            chunk.as_bytes(),
+        );
     }
 
-    /// Very small egui renderer that shows the collected PTY buffer.
-    pub fn ui(&self, ui: &mut egui::Ui) {
+    /// Very small egui renderer that shows the collected PTY screen.
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
         use egui::ScrollArea;
 
         ui.heading("PTY Output");
+
+        ui.horizontal(|ui| {
+            if ui.button("Quit ffmpeg gracefully").clicked() {
+                let _ = self.send_input(b"q");
+            }
+            if ui.button("Ctrl-C").clicked() {
+                let _ = self.send_input(b"\x03");
+            }
+        });
+
+        let events = ui.ctx().input(|input| input.events.clone());
+        for event in &events {
+            match event {
+                egui::Event::Text(text) => {
+                    let _ = self.send_input(text.as_bytes());
+                }
+                egui::Event::Key {
+                    key: egui::Key::Enter,
+                    pressed: true,
+                    ..
+                } => {
+                    let _ = self.send_input(b"\r");
+                }
+                egui::Event::Key {
+                    key: egui::Key::C,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if modifiers.ctrl => {
+                    let _ = self.send_input(b"\x03");
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(progress) = self.progress() {
+            let label = match progress.eta {
+                Some(eta) => format!(
+                    "{:.0}% · {:.2}x · ETA {}",
+                    progress.fraction * 100.0,
+                    progress.speed,
+                    format_eta(eta)
+                ),
+                None => format!("{:.0}% · {:.2}x", progress.fraction * 100.0, progress.speed),
+            };
+            ui.add(egui::ProgressBar::new(progress.fraction).text(label));
+        }
+
+        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        let glyph_advance = ui
+            .fonts(|fonts| fonts.glyph_width(&font_id, 'M'))
+            .max(1.0);
+        let row_height = ui.fonts(|fonts| fonts.row_height(&font_id)).max(1.0);
+
+        let available = ui.available_size();
+        let cols = ((available.x / glyph_advance).floor() as u16).max(1);
+        let rows = ((available.y / row_height).floor() as u16).max(1);
+        if let Err(err) = self.resize(cols, rows) {
+            ui.colored_label(ui.visuals().error_fg_color, format!("resize failed: {err}"));
+        }
+
+        if let Some(message) = self.last_error() {
+            ui.colored_label(ui.visuals().error_fg_color, message);
+        }
+
         ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
-            let buffer = self.buffer.lock().expect("terminal buffer poisoned");
-            ui.code(buffer.as_str());
+            let screen = lock(&self.screen);
+            let mut job = egui::text::LayoutJob::default();
+            job.wrap.max_width = screen.cols() as f32 * glyph_advance;
+            for row in 0..screen.rows() {
+                for cell in screen.row(row) {
+                    let mut format = egui::TextFormat {
+                        font_id: font_id.clone(),
+                        color: cell.fg,
+                        background: cell.bg,
+                        ..Default::default()
+                    };
+                    if cell.bold {
+                        format.color = format.color.gamma_multiply(1.2);
+                    }
+                    job.append(&cell.ch.to_string(), 0.0, format);
+                }
+                job.append("\n", 0.0, egui::TextFormat::default());
+            }
+            ui.label(job);
         });
     }
 
     /// Helper for spawning a command on the PTY and streaming its output into the buffer.
-    pub fn spawn_command(
-        &mut self,
-        command: CommandBuilder,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn spawn_command(&mut self, command: CommandBuilder) -> Result<()> {
         if let Some(handle) = self.reader_thread.take() {
             let _ = handle.join();
         }
@@ -66,9 +255,34 @@ impl PtyTerminal {
 
         self.push_output(CommandStream::Stdout, "Launching command...\n");
 
-        let mut child = self.pair.slave.spawn_command(command)?;
-        let reader = self.pair.master.try_clone_reader()?;
-        let buffer_for_output = Arc::clone(&self.buffer);
+        let program = command.get_argv()[0].to_string_lossy().into_owned();
+        let mut child =
+            self.pair
+                .slave
+                .spawn_command(command)
+                .map_err(|source| Error::SpawnProcess {
+                    program: program.clone(),
+                    source: source.into(),
+                })?;
+        self.writer = Some(
+            self.pair
+                .master
+                .take_writer()
+                .map_err(|source| Error::TakeWriter {
+                    source: source.into(),
+                })?,
+        );
+        let reader = self
+            .pair
+            .master
+            .try_clone_reader()
+            .map_err(|source| Error::CloneReader {
+                source: source.into(),
+            })?;
+        let screen_for_output = Arc::clone(&self.screen);
+        let progress_for_output = Arc::clone(&self.progress);
+        let log_for_output = Arc::clone(&self.log);
+        let error_for_output = Arc::clone(&self.last_error);
 
         self.reader_thread = Some(thread::spawn(move || {
             let mut reader = reader;
@@ -77,57 +291,87 @@ impl PtyTerminal {
                 match reader.read(&mut buf) {
                     Ok(0) => break,
                     Ok(n) => {
-                        let chunk = String::from_utf8_lossy(&buf[..n]);
-                        PtyTerminal::write_chunk(&buffer_for_output, CommandStream::Stdout, &chunk);
-                    }
-                    Err(err) => {
-                        let message = format!("reader error: {err}\n");
                         PtyTerminal::write_chunk(
-                            &buffer_for_output,
-                            CommandStream::Stderr,
-                            &message,
+                            &screen_for_output,
+                            &progress_for_output,
+                            &log_for_output,
+                            CommandStream::Stdout,
+                            &buf[..n],
                         );
+                    }
+                    Err(source) => {
+                        *lock(&error_for_output) = Some(Error::ReadOutput { source }.to_string());
                         break;
                     }
                 }
             }
         }));
 
-        let buffer_for_wait = Arc::clone(&self.buffer);
+        let screen_for_wait = Arc::clone(&self.screen);
+        let progress_for_wait = Arc::clone(&self.progress);
+        let log_for_wait = Arc::clone(&self.log);
+        let error_for_wait = Arc::clone(&self.last_error);
         self.wait_thread = Some(thread::spawn(move || match child.wait() {
             Ok(status) => {
                 if status.success() {
                     PtyTerminal::write_chunk(
-                        &buffer_for_wait,
+                        &screen_for_wait,
+                        &progress_for_wait,
+                        &log_for_wait,
                         CommandStream::Stdout,
-                        "Command completed successfully.\n",
+                        b"Command completed successfully.\n",
                     );
                 } else {
                     let notice = format!("Command exited with status {}.\n", status.exit_code());
-                    PtyTerminal::write_chunk(&buffer_for_wait, CommandStream::Stderr, &notice);
+                    PtyTerminal::write_chunk(
+                        &screen_for_wait,
+                        &progress_for_wait,
+                        &log_for_wait,
+                        CommandStream::Stderr,
+                        notice.as_bytes(),
+                    );
                 }
             }
-            Err(err) => {
-                let message = format!("Failed to wait on command: {err}\n");
-                PtyTerminal::write_chunk(&buffer_for_wait, CommandStream::Stderr, &message);
+            Err(source) => {
+                *lock(&error_for_wait) = Some(Error::Wait { source }.to_string());
             }
         }));
 
         Ok(())
     }
 
-    fn write_chunk(buffer: &Arc<Mutex<String>>, stream: CommandStream, chunk: &str) {
-        let mut buffer = buffer.lock().expect("terminal buffer poisoned");
+    fn write_chunk(
+        screen: &Arc<Mutex<Screen>>,
+        progress: &Arc<Mutex<ProgressTracker>>,
+        log: &Arc<Mutex<Option<Box<dyn Write + Send>>>>,
+        stream: CommandStream,
+        chunk: &[u8],
+    ) {
+        if let Some(log) = lock(log).as_mut() {
+            let _ = log.write_all(chunk);
+        }
+        let mut screen = lock(screen);
         match stream {
-            CommandStream::Stdout => buffer.push_str(chunk),
+            CommandStream::Stdout => screen.feed(chunk),
             CommandStream::Stderr => {
-                if !buffer.ends_with('\n') {
-                    buffer.push('\n');
-                }
-                buffer.push_str("[stderr]\n");
-                buffer.push_str(chunk);
+                screen.feed(b"\r\n[stderr]\r\n");
+                screen.feed(chunk);
             }
         }
+        lock(progress).feed(chunk);
+    }
+}
+
+/// Formats a [`std::time::Duration`] ETA as `H:MM:SS`/`M:SS` for display.
+fn format_eta(eta: std::time::Duration) -> String {
+    let total_seconds = eta.as_secs();
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+    let seconds = total_seconds % 60;
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
     }
 }
 
@@ -137,8 +381,12 @@ struct TerminalApp {
 }
 
 impl TerminalApp {
-    fn new(cmd: CommandBuilder) -> Self {
-        let mut terminal = PtyTerminal::new().expect("failed to open PTY");
+    fn new(cmd: CommandBuilder, output_path: &Path) -> Result<Self> {
+        let mut terminal = PtyTerminal::new()?;
+        if let Err(err) = terminal.set_log_file_next_to(output_path) {
+            let message = format!("Failed to open session log: {err}\n");
+            terminal.push_output(CommandStream::Stderr, &message);
+        }
         terminal.push_output(
             CommandStream::Stdout,
             "PTY initialized. Ready to attach commands.\n",
@@ -147,7 +395,7 @@ impl TerminalApp {
             let message = format!("Failed to spawn command: {err}\n");
             terminal.push_output(CommandStream::Stderr, &message);
         }
-        Self { terminal }
+        Ok(Self { terminal })
     }
 }
 
@@ -159,7 +407,7 @@ impl App for TerminalApp {
     }
 }
 
-fn main() -> Result<(), eframe::Error> {
+fn main() -> std::result::Result<(), eframe::Error> {
     let options = eframe::NativeOptions::default();
     let target = rfd::FileDialog::new()
         .set_title("変換する対象を選択してください")
@@ -184,11 +432,13 @@ fn main() -> Result<(), eframe::Error> {
         exit(1);
     }
 
+    let output = output.unwrap();
+
     let mut command = CommandBuilder::new("ffmpeg");
     command.args(vec![
         "-i",
         target.unwrap().to_str().unwrap(),
-        output.unwrap().to_str().unwrap(),
+        output.to_str().unwrap(),
     ]);
 
     let command_for_app = command.clone();
@@ -196,6 +446,10 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "simpleffmpeg",
         options,
-        Box::new(move |_cc| Ok(Box::new(TerminalApp::new(command_for_app.clone())))),
+        Box::new(move |_cc| {
+            TerminalApp::new(command_for_app.clone(), &output)
+                .map(|app| Box::new(app) as Box<dyn App>)
+                .map_err(|err| -> Box<dyn std::error::Error + Send + Sync> { Box::new(err) })
+        }),
     )
 }
@@ -0,0 +1,103 @@
+//! Scrapes ffmpeg's human-readable progress output (`frame=... fps=...
+//! time=HH:MM:SS.ms ... speed=Nx`) out of the raw PTY byte stream, so the UI
+//! can show a percentage and ETA instead of just scrolling text.
+
+use std::time::Duration;
+
+/// A snapshot of how far along the running ffmpeg command is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressInfo {
+    pub fraction: f32,
+    pub eta: Option<Duration>,
+    pub speed: f32,
+}
+
+/// Incrementally parses ffmpeg's status line out of raw bytes as they arrive.
+#[derive(Debug, Default)]
+pub struct ProgressTracker {
+    total_duration: Option<Duration>,
+    current_time: Option<Duration>,
+    speed: Option<f32>,
+    /// The tail of the last chunk that hadn't seen a `\r`/`\n` yet, carried
+    /// over so a field split across two PTY reads still gets parsed whole.
+    carry: String,
+}
+
+impl ProgressTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds a raw chunk of bytes read from the PTY. ffmpeg's status fields
+    /// are plain ASCII, so lossy UTF-8 conversion is fine even mid-escape.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.carry.push_str(&String::from_utf8_lossy(chunk));
+        let mut segments: Vec<String> =
+            self.carry.split(['\r', '\n']).map(str::to_string).collect();
+        self.carry = segments.pop().unwrap_or_default();
+        for segment in &segments {
+            self.scan_segment(segment);
+        }
+    }
+
+    fn scan_segment(&mut self, segment: &str) {
+        if self.total_duration.is_none() {
+            if let Some(rest) = segment.trim_start().strip_prefix("Duration: ") {
+                let ts = rest.split(',').next().unwrap_or("").trim();
+                if let Some(duration) = parse_timestamp(ts) {
+                    self.total_duration = Some(duration);
+                }
+            }
+        }
+
+        if !segment.contains("time=") {
+            return;
+        }
+
+        for field in segment.split_whitespace() {
+            if let Some(value) = field.strip_prefix("time=") {
+                if let Some(duration) = parse_timestamp(value) {
+                    self.current_time = Some(duration);
+                }
+            } else if let Some(value) = field.strip_prefix("speed=") {
+                if let Some(value) = value.strip_suffix('x') {
+                    if let Ok(speed) = value.parse::<f32>() {
+                        self.speed = Some(speed);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Computes the current fraction complete and ETA, once enough of the
+    /// status line has been observed to do so.
+    pub fn progress(&self) -> Option<ProgressInfo> {
+        let total = self.total_duration?;
+        let current = self.current_time?;
+        let speed = self.speed.filter(|s| *s > 0.0)?;
+        if total.is_zero() {
+            return None;
+        }
+
+        let fraction = (current.as_secs_f32() / total.as_secs_f32()).clamp(0.0, 1.0);
+        let remaining = total.saturating_sub(current).as_secs_f32();
+        let eta = Duration::try_from_secs_f32(remaining / speed).ok();
+
+        Some(ProgressInfo {
+            fraction,
+            eta,
+            speed,
+        })
+    }
+}
+
+/// Parses an ffmpeg `HH:MM:SS.ms` timestamp into a [`Duration`].
+fn parse_timestamp(ts: &str) -> Option<Duration> {
+    let mut parts = ts.trim().splitn(3, ':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(Duration::from_secs_f64(
+        hours * 3600.0 + minutes * 60.0 + seconds,
+    ))
+}
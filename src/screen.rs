@@ -0,0 +1,279 @@
+//! A small VTE-driven terminal screen model.
+//!
+//! ffmpeg's progress line rewrites itself in place with `\r` and dresses
+//! itself up with SGR color codes, which a plain `String` buffer can't
+//! represent (it just accumulates duplicate lines and escape garbage).
+//! [`Screen`] feeds raw bytes through [`vte::Parser`] into a fixed grid of
+//! [`Cell`]s so callers can render an actual mini-terminal.
+
+use egui::Color32;
+use vte::{Params, Parser, Perform};
+
+/// One character cell in the terminal grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: Color32,
+    pub bg: Color32,
+    pub bold: bool,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            fg: Color32::LIGHT_GRAY,
+            bg: Color32::TRANSPARENT,
+            bold: false,
+        }
+    }
+}
+
+/// A fixed-size grid of cells plus cursor/attribute state, updated by
+/// feeding it raw PTY output.
+pub struct Screen {
+    cols: usize,
+    rows: usize,
+    cells: Vec<Cell>,
+    cursor_col: usize,
+    cursor_row: usize,
+    cur_fg: Color32,
+    cur_bg: Color32,
+    cur_bold: bool,
+    parser: Parser,
+}
+
+impl Screen {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+        Self {
+            cols,
+            rows,
+            cells: vec![Cell::default(); cols * rows],
+            cursor_col: 0,
+            cursor_row: 0,
+            cur_fg: Color32::LIGHT_GRAY,
+            cur_bg: Color32::TRANSPARENT,
+            cur_bold: false,
+            parser: Parser::new(),
+        }
+    }
+
+    /// Resizes the grid in place, preserving whatever overlaps the old one.
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+        if cols == self.cols && rows == self.rows {
+            return;
+        }
+        let mut cells = vec![Cell::default(); cols * rows];
+        for row in 0..self.rows.min(rows) {
+            for col in 0..self.cols.min(cols) {
+                cells[row * cols + col] = self.cells[row * self.cols + col];
+            }
+        }
+        self.cells = cells;
+        self.cols = cols;
+        self.rows = rows;
+        self.cursor_col = self.cursor_col.min(cols - 1);
+        self.cursor_row = self.cursor_row.min(rows - 1);
+    }
+
+    /// Feeds a chunk of raw bytes read from the PTY through the VTE parser.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        let mut parser = std::mem::take(&mut self.parser);
+        for byte in bytes {
+            parser.advance(self, *byte);
+        }
+        self.parser = parser;
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the cells making up a single row, for rendering.
+    pub fn row(&self, row: usize) -> &[Cell] {
+        &self.cells[row * self.cols..(row + 1) * self.cols]
+    }
+
+    fn cell_at_mut(&mut self, row: usize, col: usize) -> &mut Cell {
+        &mut self.cells[row * self.cols + col]
+    }
+
+    fn put_char(&mut self, ch: char) {
+        if self.cursor_col >= self.cols {
+            self.cursor_col = 0;
+            self.newline();
+        }
+        let (row, col) = (self.cursor_row, self.cursor_col);
+        *self.cell_at_mut(row, col) = Cell {
+            ch,
+            fg: self.cur_fg,
+            bg: self.cur_bg,
+            bold: self.cur_bold,
+        };
+        self.cursor_col += 1;
+    }
+
+    fn newline(&mut self) {
+        if self.cursor_row + 1 >= self.rows {
+            self.scroll_up();
+        } else {
+            self.cursor_row += 1;
+        }
+    }
+
+    fn scroll_up(&mut self) {
+        self.cells.drain(0..self.cols);
+        self.cells.resize(self.cols * self.rows, Cell::default());
+    }
+
+    fn erase_line_from_cursor(&mut self) {
+        let row = self.cursor_row;
+        for col in self.cursor_col..self.cols {
+            *self.cell_at_mut(row, col) = Cell::default();
+        }
+    }
+
+    fn erase_line(&mut self) {
+        let row = self.cursor_row;
+        for col in 0..self.cols {
+            *self.cell_at_mut(row, col) = Cell::default();
+        }
+    }
+
+    fn erase_display_from_cursor(&mut self) {
+        self.erase_line_from_cursor();
+        for row in (self.cursor_row + 1)..self.rows {
+            for col in 0..self.cols {
+                *self.cell_at_mut(row, col) = Cell::default();
+            }
+        }
+    }
+
+    fn apply_sgr(&mut self, params: &Params) {
+        if params.is_empty() {
+            self.cur_fg = Color32::LIGHT_GRAY;
+            self.cur_bg = Color32::TRANSPARENT;
+            self.cur_bold = false;
+            return;
+        }
+        for param in params.iter() {
+            let code = param.first().copied().unwrap_or(0);
+            match code {
+                0 => {
+                    self.cur_fg = Color32::LIGHT_GRAY;
+                    self.cur_bg = Color32::TRANSPARENT;
+                    self.cur_bold = false;
+                }
+                1 => self.cur_bold = true,
+                22 => self.cur_bold = false,
+                30..=37 => self.cur_fg = ansi_color(code - 30, self.cur_bold),
+                39 => self.cur_fg = Color32::LIGHT_GRAY,
+                40..=47 => self.cur_bg = ansi_color(code - 40, false),
+                49 => self.cur_bg = Color32::TRANSPARENT,
+                90..=97 => self.cur_fg = ansi_color(code - 90, true),
+                100..=107 => self.cur_bg = ansi_color(code - 100, true),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn ansi_color(index: u16, bright: bool) -> Color32 {
+    match (index, bright) {
+        (0, false) => Color32::BLACK,
+        (1, false) => Color32::DARK_RED,
+        (2, false) => Color32::DARK_GREEN,
+        (3, false) => Color32::from_rgb(128, 128, 0),
+        (4, false) => Color32::DARK_BLUE,
+        (5, false) => Color32::from_rgb(128, 0, 128),
+        (6, false) => Color32::from_rgb(0, 128, 128),
+        (7, false) => Color32::LIGHT_GRAY,
+        (0, true) => Color32::DARK_GRAY,
+        (1, true) => Color32::RED,
+        (2, true) => Color32::GREEN,
+        (3, true) => Color32::YELLOW,
+        (4, true) => Color32::BLUE,
+        (5, true) => Color32::from_rgb(255, 0, 255),
+        (6, true) => Color32::from_rgb(0, 255, 255),
+        (7, true) => Color32::WHITE,
+        _ => Color32::LIGHT_GRAY,
+    }
+}
+
+impl Perform for Screen {
+    fn print(&mut self, c: char) {
+        self.put_char(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\r' => self.cursor_col = 0,
+            b'\n' => self.newline(),
+            0x08 => self.cursor_col = self.cursor_col.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, _intermediates: &[u8], _ignore: bool, action: char) {
+        match action {
+            'm' => self.apply_sgr(params),
+            'H' | 'f' => {
+                let mut iter = params.iter();
+                let row = iter
+                    .next()
+                    .and_then(|p| p.first().copied())
+                    .unwrap_or(1)
+                    .max(1) as usize
+                    - 1;
+                let col = iter
+                    .next()
+                    .and_then(|p| p.first().copied())
+                    .unwrap_or(1)
+                    .max(1) as usize
+                    - 1;
+                self.cursor_row = row.min(self.rows - 1);
+                self.cursor_col = col.min(self.cols - 1);
+            }
+            'A' => {
+                let n = params.iter().next().and_then(|p| p.first().copied()).unwrap_or(1) as usize;
+                self.cursor_row = self.cursor_row.saturating_sub(n.max(1));
+            }
+            'B' => {
+                let n = params.iter().next().and_then(|p| p.first().copied()).unwrap_or(1) as usize;
+                self.cursor_row = (self.cursor_row + n.max(1)).min(self.rows - 1);
+            }
+            'C' => {
+                let n = params.iter().next().and_then(|p| p.first().copied()).unwrap_or(1) as usize;
+                self.cursor_col = (self.cursor_col + n.max(1)).min(self.cols - 1);
+            }
+            'D' => {
+                let n = params.iter().next().and_then(|p| p.first().copied()).unwrap_or(1) as usize;
+                self.cursor_col = self.cursor_col.saturating_sub(n.max(1));
+            }
+            'G' => {
+                let n = params.iter().next().and_then(|p| p.first().copied()).unwrap_or(1) as usize;
+                self.cursor_col = n.saturating_sub(1).min(self.cols - 1);
+            }
+            'K' => match params.iter().next().and_then(|p| p.first().copied()).unwrap_or(0) {
+                0 => self.erase_line_from_cursor(),
+                2 => self.erase_line(),
+                _ => self.erase_line_from_cursor(),
+            },
+            'J' => match params.iter().next().and_then(|p| p.first().copied()).unwrap_or(0) {
+                2 | 3 => {
+                    self.cells.fill(Cell::default());
+                }
+                _ => self.erase_display_from_cursor(),
+            },
+            _ => {}
+        }
+    }
+}